@@ -5,11 +5,10 @@ use rand::prelude::*;
 use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
 use std::iter::repeat;
-use std::{fs, io, str};
+use std::{fs, str};
+use unidecode::unidecode;
 
-// TODO memoize some stuff?
-
-const WORD_LENGTH: usize = 5;
+const DEFAULT_WORD_LENGTH: usize = 5;
 const GUESS_COUNT: usize = 6;
 
 #[derive(Parser)]
@@ -28,105 +27,343 @@ struct Args {
     #[clap(long)]
     #[clap(arg_enum)]
     format: Option<OutputFormat>,
+    /// The length of words to play with, for non-standard Wordle variants
+    #[clap(short, long, default_value_t = DEFAULT_WORD_LENGTH)]
+    length: usize,
+    /// Path to a custom dictionary, one word per line (accents are normalized to ASCII).
+    /// Lines may have a second whitespace-separated column giving the word's frequency.
+    #[clap(short, long)]
+    wordlist: Option<String>,
+    /// Path to a file mapping word to frequency count, one "word count" pair per line
+    #[clap(long)]
+    frequencies: Option<String>,
+    /// Treat the art as an actual 6-guess game: the final row must be all green and is forced
+    /// to the solution, which no earlier row plays. Other guesses avoid repeats only while a
+    /// row still has an unused candidate word
+    #[clap(long)]
+    valid_game: bool,
 }
 
 #[derive(Clone, ArgEnum)]
 enum OutputFormat {
     Example,
     Full,
+    Emoji,
+}
+
+/// A tile's color after a guess: green (right spot), yellow (wrong spot), or gray (absent).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Tile {
+    Green,
+    Yellow,
+    Gray,
+}
+
+/// An optional restriction on which letter may occupy a cell, independent of its color.
+#[derive(Clone, Debug)]
+enum CellConstraint {
+    Any,
+    Exact(char),
+    OneOf(HashSet<char>),
+}
+
+impl CellConstraint {
+    fn matches(&self, letter: char) -> bool {
+        match self {
+            CellConstraint::Any => true,
+            CellConstraint::Exact(expected) => letter == *expected,
+            CellConstraint::OneOf(letters) => letters.contains(&letter),
+        }
+    }
 }
 
 fn main() {
     let args = Args::parse();
+    let word_length = args.length;
+    // Normalized the same way as dictionary entries, so comparisons (length, signature,
+    // constraint matching) see the same representation on both sides.
+    let solution = unidecode(&args.solution.to_lowercase());
 
     assert!(
-        args.solution.len() == WORD_LENGTH,
-        "Solution should be 5 letters",
+        solution.chars().count() == word_length,
+        "Solution should be {} letters",
+        word_length,
     );
 
-    let all_words: Vec<&str> = include_str!("../dict.txt").lines().collect();
+    let (all_words, mut frequencies) = load_dictionary(args.wordlist.as_deref(), word_length);
+    if let Some(path) = &args.frequencies {
+        frequencies = load_frequencies(path);
+    }
 
-    let goal_shape = match (args.pattern, args.artfile) {
-        (Some(pattern), _) => pattern_from_string(&pattern),
-        (_, Some(artfile)) => pattern_from_file(&artfile).expect("Could not read artfile"),
+    let raw_pattern = match (&args.pattern, &args.artfile) {
+        (Some(pattern), _) => pattern.clone(),
+        (_, Some(artfile)) => fs::read_to_string(artfile).expect("Could not read artfile"),
         // Arg validation requires that one of the above must match.
         // Ideally `clap` would allow reading in as an enum in such cases..
         _ => unreachable!(),
     };
+    let goal_shape = pattern_from_string(&raw_pattern, word_length);
+    // Trailing newlines (virtually universal in saved artfiles) would otherwise split off a
+    // trailing empty "row" and make the final row look like padding instead of real art.
+    let given_rows = raw_pattern
+        .trim_end_matches(['\n', '/'])
+        .split(&['/', '\n'][..])
+        .count()
+        .clamp(1, GUESS_COUNT);
+
+    if args.valid_game {
+        let final_row = given_rows.saturating_sub(1);
+        assert!(
+            goal_shape[final_row]
+                .iter()
+                .all(|(tile, _)| *tile == Tile::Green),
+            "--valid-game requires the art's final row to be all green",
+        );
+    }
 
-    let answer: Vec<Vec<&str>> = goal_shape
+    let all_words: Vec<&str> = all_words.iter().map(String::as_str).collect();
+    let signature_index = build_signature_index(&all_words, &solution);
+    let mut answer: Vec<Vec<&str>> = goal_shape
         .iter()
-        .map(|goal_row| find_matches(&all_words, &args.solution, goal_row))
+        .map(|goal_row| {
+            signature_index
+                .get(&encode_goal_signature(goal_row))
+                .into_iter()
+                .flatten()
+                .filter(|&&word| matches_constraints(word, goal_row))
+                .cloned()
+                .collect()
+        })
         .collect();
 
-    let formatter = match args.format.unwrap_or(OutputFormat::Example) {
-        OutputFormat::Example => format_example,
-        OutputFormat::Full => format_full,
+    // In a valid game only the final row plays the solution: it is dropped from every earlier
+    // row's candidates and reserved so the sampling below treats it as already used.
+    let mut reserved_word = None;
+    if args.valid_game {
+        let final_row = given_rows.saturating_sub(1);
+        let solution_word = all_words
+            .iter()
+            .find(|&&word| word == solution)
+            .copied()
+            .expect("--valid-game requires the solution to be in the dictionary");
+        assert!(
+            matches_constraints(solution_word, &goal_shape[final_row]),
+            "--valid-game: the solution doesn't satisfy the final row's letter constraints",
+        );
+        for row in &mut answer[..final_row] {
+            row.retain(|&word| word != solution_word);
+        }
+        answer[final_row] = vec![solution_word];
+        reserved_word = Some(solution_word);
+    }
+
+    let output = match args.format.unwrap_or(OutputFormat::Example) {
+        OutputFormat::Example => format_example(&answer, &frequencies, reserved_word),
+        OutputFormat::Full => format_full(&answer),
+        OutputFormat::Emoji => {
+            format_emoji(&solution, &answer, &frequencies, given_rows, reserved_word)
+        }
     };
-    println!("{}", formatter(&answer));
+    println!("{}", output);
 }
 
-fn pattern_from_string(string: &str) -> Vec<Vec<bool>> {
+fn pattern_from_string(string: &str, word_length: usize) -> Vec<Vec<(Tile, CellConstraint)>> {
     string
         .split(&['/', '\n'][..])
-        .map(pattern_for_line)
-        .chain(repeat(vec![false; WORD_LENGTH]))
+        .map(|line| pattern_for_line(line, word_length))
+        .chain(repeat(vec![(Tile::Gray, CellConstraint::Any); word_length]))
         .take(GUESS_COUNT)
         .collect()
 }
 
-fn pattern_from_file(path: &str) -> io::Result<Vec<Vec<bool>>> {
-    let contents = fs::read_to_string(path)?;
-    Ok(pattern_from_string(&contents))
+// A line is a sequence of cells, each coloring plus an optional constraint on its letter (`a`
+// for an exact letter, `[aeiou]` for a class, `*` or nothing for "any"). When no cell needs a
+// constraint, cells may be written as a single compact run of color characters with no spaces;
+// otherwise cells are whitespace-separated so multi-character tokens like `[aeiou]` are possible.
+fn pattern_for_line<S: AsRef<str>>(line: S, word_length: usize) -> Vec<(Tile, CellConstraint)> {
+    let line = line.as_ref();
+    let default = (Tile::Gray, CellConstraint::Any);
+
+    if line.contains(char::is_whitespace) {
+        line.split_whitespace()
+            .map(parse_cell)
+            .chain(repeat(default))
+            .take(word_length)
+            .collect()
+    } else {
+        line.chars()
+            .map(|c| (parse_tile(c), CellConstraint::Any))
+            .chain(repeat(default))
+            .take(word_length)
+            .collect()
+    }
+}
+
+fn parse_tile(c: char) -> Tile {
+    match c {
+        'G' | 'g' | '🟩' => Tile::Green,
+        'Y' | 'y' | '🟨' => Tile::Yellow,
+        _ => Tile::Gray,
+    }
+}
+
+fn is_tile_marker(c: char) -> bool {
+    matches!(c, 'G' | 'g' | '🟩' | 'Y' | 'y' | '🟨' | '.' | '⬛')
+}
+
+// A tile marker (G/Y/./emoji) is only consumed as the color when the token actually starts
+// with one; otherwise the whole token is a bare constraint (`a`, `[aeiou]`, `*`) on a gray cell.
+fn parse_cell(token: &str) -> (Tile, CellConstraint) {
+    let mut chars = token.chars();
+    match chars.next() {
+        Some(c) if is_tile_marker(c) => (parse_tile(c), parse_constraint(chars.as_str())),
+        _ => (Tile::Gray, parse_constraint(token)),
+    }
 }
 
-fn pattern_for_line<S: AsRef<str>>(line: S) -> Vec<bool> {
-    line.as_ref()
-        .chars()
-        .chain(repeat(' '))
-        .take(WORD_LENGTH)
-        .map(|c| c != ' ')
+fn parse_constraint(spec: &str) -> CellConstraint {
+    match spec.strip_prefix('[').and_then(|spec| spec.strip_suffix(']')) {
+        Some(class) => CellConstraint::OneOf(class.chars().collect()),
+        None => match spec.chars().next() {
+            Some('*') | None => CellConstraint::Any,
+            Some(letter) => CellConstraint::Exact(letter),
+        },
+    }
+}
+
+fn matches_constraints(word: &str, goal_row: &[(Tile, CellConstraint)]) -> bool {
+    word.chars()
+        .zip(goal_row.iter())
+        .all(|(letter, (_, constraint))| constraint.matches(letter))
+}
+
+/// Loads the dictionary from `path` (or the bundled default), lowercasing, transliterating
+/// accents to ASCII, deduplicating, and filtering down to words of `word_length`. A second
+/// whitespace-separated column on a line, if present, is read as that word's frequency.
+fn load_dictionary(path: Option<&str>, word_length: usize) -> (Vec<String>, HashMap<String, f64>) {
+    let contents = match path {
+        Some(path) => fs::read_to_string(path).expect("Could not read wordlist"),
+        None => include_str!("../dict.txt").to_string(),
+    };
+
+    let mut seen = HashSet::new();
+    let mut frequencies = HashMap::new();
+    let mut words = Vec::new();
+
+    for line in contents.lines() {
+        let mut fields = line.split_whitespace();
+        let word = match fields.next() {
+            Some(word) => unidecode(&word.to_lowercase()),
+            None => continue,
+        };
+        if word.chars().count() != word_length || !seen.insert(word.clone()) {
+            continue;
+        }
+        if let Some(frequency) = fields.next().and_then(|field| field.parse().ok()) {
+            frequencies.insert(word.clone(), frequency);
+        }
+        words.push(word);
+    }
+
+    (words, frequencies)
+}
+
+/// Loads a word-to-frequency mapping from a file of "word count" pairs, one per line.
+fn load_frequencies(path: &str) -> HashMap<String, f64> {
+    let contents = fs::read_to_string(path).expect("Could not read frequencies file");
+
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let word = unidecode(&fields.next()?.to_lowercase());
+            let frequency: f64 = fields.next()?.parse().ok()?;
+            Some((word, frequency))
+        })
         .collect()
 }
 
-fn find_matches<'a>(all_words: &[&'a str], solution: &str, goal_row: &[bool]) -> Vec<&'a str> {
-    all_words
+fn tile_digit(tile: Tile) -> u64 {
+    match tile {
+        Tile::Gray => 0,
+        Tile::Yellow => 1,
+        Tile::Green => 2,
+    }
+}
+
+// Encodes a row of tile colors as a base-3 integer (0=gray, 1=yellow, 2=green), giving a
+// compact key that two rows share iff they have the exact same coloring. `u64` comfortably
+// covers every `--length` this tool could reasonably be asked to support (3^40 still fits).
+fn encode_signature(colors: &[Tile]) -> u64 {
+    colors.iter().fold(0, |key, &tile| key * 3 + tile_digit(tile))
+}
+
+fn encode_goal_signature(goal_row: &[(Tile, CellConstraint)]) -> u64 {
+    goal_row
+        .iter()
+        .fold(0, |key, (tile, _)| key * 3 + tile_digit(*tile))
+}
+
+// Buckets every dictionary word by the color signature it produces against `solution`, so each
+// `goal_row` is a single O(1) lookup instead of a fresh scan over the whole dictionary.
+fn build_signature_index<'a>(all_words: &[&'a str], solution: &str) -> HashMap<u64, Vec<&'a str>> {
+    let solution = solution.to_lowercase();
+    let signatures: Vec<(u64, &str)> = all_words
         .par_iter()
-        .cloned()
-        .filter(|&test_word| does_match(test_word, &solution.to_lowercase(), goal_row))
-        .collect()
+        .map(|&word| (encode_signature(&evaluate_guess(word, &solution)), word))
+        .collect();
+
+    let mut index: HashMap<u64, Vec<&str>> = HashMap::new();
+    for (key, word) in signatures {
+        index.entry(key).or_default().push(word);
+    }
+    index
 }
 
-fn does_match(test_word: &str, solution: &str, goal_row: &[bool]) -> bool {
-    let mut unused_counts: HashMap<char, usize> = HashMap::with_capacity(WORD_LENGTH);
+// Two-pass color evaluation: greens first (and removed from the solution's letter pool),
+// then yellows for guessed letters the pool still owes.
+fn evaluate_guess(test_word: &str, solution: &str) -> Vec<Tile> {
+    let mut remaining_counts: HashMap<char, usize> = HashMap::with_capacity(solution.len());
     for char in solution.chars() {
-        let count = unused_counts.entry(char).or_default();
-        *count += 1;
+        *remaining_counts.entry(char).or_default() += 1;
     }
 
-    for (test_char, solution_char, &should_match) in
-        izip!(test_word.chars(), solution.chars(), goal_row.iter())
+    let mut colors: Vec<Tile> = test_word.chars().map(|_| Tile::Gray).collect();
+
+    for (color, test_char, solution_char) in
+        izip!(colors.iter_mut(), test_word.chars(), solution.chars())
     {
-        let does_match = test_char == solution_char;
-        if should_match != does_match {
-            return false;
+        if test_char == solution_char {
+            *color = Tile::Green;
+            *remaining_counts.entry(test_char).or_default() -= 1;
         }
-        if does_match {
-            let count = unused_counts.entry(test_char).or_default();
+    }
+
+    for (color, test_char) in colors.iter_mut().zip(test_word.chars()) {
+        if *color == Tile::Green {
+            continue;
+        }
+        let count = remaining_counts.entry(test_char).or_default();
+        if *count > 0 {
             *count -= 1;
+            *color = Tile::Yellow;
         }
     }
 
-    // One final iteration over the non-matching charactes to ensure none of them will be yellow.
-    test_word
-        .chars()
-        .enumerate()
-        .all(|(i, c)| goal_row[i] || unused_counts.get(&c).unwrap_or(&0) == &0)
+    colors
 }
 
-fn format_example(answer: &[Vec<&str>]) -> String {
-    let mut lines = Vec::with_capacity(GUESS_COUNT);
+// Words missing from the frequency data are treated as vanishingly rare rather than excluded.
+const FREQUENCY_EPSILON: f64 = 1e-6;
+
+fn pick_example_words<'a>(
+    answer: &[Vec<&'a str>],
+    frequencies: &HashMap<String, f64>,
+    reserved: Option<&'a str>,
+) -> Vec<Option<&'a str>> {
+    let mut picks = Vec::with_capacity(GUESS_COUNT);
     let mut used_words = HashSet::with_capacity(GUESS_COUNT - 1);
+    used_words.extend(reserved);
     let mut rng = thread_rng();
 
     for all_row_answers in answer {
@@ -140,19 +377,42 @@ fn format_example(answer: &[Vec<&str>]) -> String {
         } else {
             &unused_row_answers
         };
-        // TODO give dictionary weights based on actual commonality?
-        let weights: Vec<usize> = (1..=row_answers.len()).rev().map(|w| w * w).collect();
+        let weights: Vec<f64> = if frequencies.is_empty() {
+            (1..=row_answers.len())
+                .rev()
+                .map(|w| (w * w) as f64)
+                .collect()
+        } else {
+            row_answers
+                .iter()
+                .map(|word| *frequencies.get(*word).unwrap_or(&FREQUENCY_EPSILON))
+                .collect()
+        };
         match WeightedIndex::new(&weights) {
             Ok(dist) => {
                 let word = row_answers[dist.sample(&mut rng)];
-                lines.push(word.to_uppercase());
                 used_words.insert(word);
+                picks.push(Some(word));
             }
-            Err(_) => lines.push("[no solution]".to_string()),
+            Err(_) => picks.push(None),
         }
     }
 
-    lines.join("\n")
+    picks
+}
+
+fn format_example<'a>(
+    answer: &[Vec<&'a str>],
+    frequencies: &HashMap<String, f64>,
+    reserved: Option<&'a str>,
+) -> String {
+    pick_example_words(answer, frequencies, reserved)
+        .into_iter()
+        .map(|word| match word {
+            Some(word) => word.to_uppercase(),
+            None => "[no solution]".to_string(),
+        })
+        .join("\n")
 }
 
 fn format_full(answer: &[Vec<&str>]) -> String {
@@ -161,3 +421,39 @@ fn format_full(answer: &[Vec<&str>]) -> String {
         .map(|line| line.iter().cloned().map(str::to_uppercase).join(" "))
         .join("\n")
 }
+
+fn format_emoji<'a>(
+    solution: &str,
+    answer: &[Vec<&'a str>],
+    frequencies: &HashMap<String, f64>,
+    given_rows: usize,
+    reserved: Option<&'a str>,
+) -> String {
+    let solution = solution.to_lowercase();
+    let words = pick_example_words(answer, frequencies, reserved);
+    // Only the rows the user's art actually specified count as "played" — the padded, all-gray
+    // rows beyond that match huge swaths of any dictionary and would otherwise inflate the count.
+    let words = &words[..given_rows.min(words.len())];
+
+    let mut lines = vec![format!("Wordle {} {}/6", solution.to_uppercase(), given_rows)];
+
+    for word in words.iter().flatten() {
+        let squares: String = evaluate_guess(word, &solution)
+            .iter()
+            .map(|tile| match tile {
+                Tile::Green => '🟩',
+                Tile::Yellow => '🟨',
+                Tile::Gray => '⬛',
+            })
+            .collect();
+        lines.push(squares);
+    }
+
+    lines.push(String::new());
+    lines.extend(words.iter().map(|word| match word {
+        Some(word) => word.to_uppercase(),
+        None => "[no solution]".to_string(),
+    }));
+
+    lines.join("\n")
+}